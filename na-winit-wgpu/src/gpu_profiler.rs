@@ -0,0 +1,182 @@
+// GPU frame-time profiling via timestamp queries. Only constructed when the
+// adapter supports `Features::TIMESTAMP_QUERY` *and* the user opted in (see
+// `WGPU_GPU_PROFILE` in `_main`); adapters without the feature, or callers
+// who didn't ask for it, simply don't get a `GpuProfiler` and rendering
+// falls back to the previous `timestamp_writes: None` behavior.
+//
+// Readback is double-buffered so it never blocks the frame that resolved
+// it: `resolve` copies the current frame's slot's query set into that
+// slot's readback buffer,
+// `begin_readback` kicks off an async `map_async` for that slot and moves
+// on, and `poll_and_log` (called once per frame with `Maintain::Poll`, not
+// `Maintain::Wait`) drains whichever slot's mapping has completed by now --
+// typically the *previous* frame's, once the GPU has caught up.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+const QUERY_COUNT: u32 = 2;
+const FRAMES_IN_FLIGHT: usize = 2;
+
+struct QuerySlot {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    pending: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl QuerySlot {
+    fn new(device: &wgpu::Device, index: usize) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&format!("gpu profiler timestamp query set {index}")),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+
+        let buffer_size = QUERY_COUNT as u64 * std::mem::size_of::<u64>() as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("gpu profiler resolve buffer {index}")),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("gpu profiler readback buffer {index}")),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            pending: None,
+        }
+    }
+}
+
+pub struct GpuProfiler {
+    slots: [QuerySlot; FRAMES_IN_FLIGHT],
+    frame: usize,
+    period_ns: f32,
+    rolling_avg_ms: f64,
+}
+
+impl GpuProfiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self {
+            slots: std::array::from_fn(|i| QuerySlot::new(device, i)),
+            frame: 0,
+            period_ns: queue.get_timestamp_period(),
+            rolling_avg_ms: 0.0,
+        }
+    }
+
+    /// `None` when this frame's slot is still waiting on a previous
+    /// `map_async` to complete (see `begin_readback`) -- in that case the
+    /// render pass should omit `timestamp_writes` entirely for this frame
+    /// rather than record into a slot we can't resolve yet.
+    pub fn timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let slot = &self.slots[self.frame % FRAMES_IN_FLIGHT];
+        if slot.pending.is_some() {
+            return None;
+        }
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &slot.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        })
+    }
+
+    /// Resolves this frame's begin/end timestamps into its slot's readback
+    /// buffer. Must be called after the render pass that used
+    /// `timestamp_writes` ends, and before the encoder is submitted. A
+    /// no-op when `timestamp_writes` returned `None` for this frame.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let slot = &self.slots[self.frame % FRAMES_IN_FLIGHT];
+        if slot.pending.is_some() {
+            return;
+        }
+        encoder.resolve_query_set(&slot.query_set, 0..QUERY_COUNT, &slot.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &slot.resolve_buffer,
+            0,
+            &slot.readback_buffer,
+            0,
+            slot.resolve_buffer.size(),
+        );
+    }
+
+    /// Kicks off an async mapping of this frame's slot, then advances to the
+    /// next slot. Must be called after the encoder from `resolve` has been
+    /// submitted. Non-blocking: the actual CPU readback happens later, in
+    /// `poll_and_log`.
+    ///
+    /// If this slot's previous mapping hasn't completed yet -- the GPU or
+    /// driver running a frame or two behind, which is ordinary under load,
+    /// at startup, or right after chunk0-1's vsync-off toggle uncaps the
+    /// frame rate -- this is a no-op rather than a second `map_async` on an
+    /// already-mapped buffer, which wgpu hard-panics on
+    /// (`MapContext::initial_range` assert).
+    pub fn begin_readback(&mut self) {
+        let idx = self.frame % FRAMES_IN_FLIGHT;
+        self.frame += 1;
+
+        let slot = &mut self.slots[idx];
+        if slot.pending.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        slot.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+        slot.pending = Some(rx);
+    }
+
+    /// Pumps the device's callback queue without blocking, then logs any
+    /// slot whose mapping has completed since the last call -- ordinarily
+    /// the slot from a frame or two ago, once the GPU has caught up.
+    pub fn poll_and_log(&mut self, device: &wgpu::Device) {
+        device.poll(wgpu::Maintain::Poll);
+
+        for slot in &mut self.slots {
+            let Some(rx) = &slot.pending else { continue };
+            match rx.try_recv() {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => {
+                    log::warn!("gpu profiler: failed to map readback buffer: {err}");
+                    slot.pending = None;
+                    continue;
+                }
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => {
+                    slot.pending = None;
+                    continue;
+                }
+            }
+            slot.pending = None;
+
+            let ticks = {
+                let data = slot.readback_buffer.slice(..).get_mapped_range();
+                let begin = u64::from_le_bytes(data[0..8].try_into().unwrap());
+                let end = u64::from_le_bytes(data[8..16].try_into().unwrap());
+                end.saturating_sub(begin)
+            };
+            slot.readback_buffer.unmap();
+
+            let gpu_ms = ticks as f64 * self.period_ns as f64 / 1_000_000.0;
+            self.rolling_avg_ms = if self.rolling_avg_ms == 0.0 {
+                gpu_ms
+            } else {
+                self.rolling_avg_ms * 0.9 + gpu_ms * 0.1
+            };
+            log::info!(
+                "GPU time: {:.3} ms (rolling avg {:.3} ms)",
+                gpu_ms,
+                self.rolling_avg_ms
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Shader hot-reloading for desktop builds. A background thread polls the
+// shader file's mtime (`shader.wgsl`, or `shader_hdr.wgsl` for an HDR
+// swapchain), and on change reads the new source, hands it back
+// over a channel, and wakes the window so the pipeline gets rebuilt on the
+// next redraw. Android and wasm32 don't get a watcher at all: there's no
+// writable source tree to poll on-device.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+pub struct HotReloader {
+    rx: Receiver<String>,
+}
+
+impl HotReloader {
+    pub fn spawn(path: PathBuf, window: Arc<winit::window::Window>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut last_modified: Option<SystemTime> = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+
+            loop {
+                std::thread::sleep(Duration::from_millis(250));
+
+                let Ok(modified) = std::fs::metadata(&path).and_then(|metadata| metadata.modified())
+                else {
+                    continue;
+                };
+                if Some(modified) == last_modified {
+                    continue;
+                }
+                last_modified = Some(modified);
+
+                match std::fs::read_to_string(&path) {
+                    Ok(source) => {
+                        if tx.send(source).is_err() {
+                            return; // Receiver gone, app is shutting down.
+                        }
+                        window.request_redraw();
+                    }
+                    Err(err) => log::warn!("hot reload: failed to read {path:?}: {err}"),
+                }
+            }
+        });
+        Self { rx }
+    }
+
+    /// Returns the newest shader source received since the last call,
+    /// without blocking. Intermediate versions (if several edits landed
+    /// between polls) are dropped in favor of the latest.
+    pub fn try_recv_latest(&self) -> Option<String> {
+        self.rx.try_iter().last()
+    }
+}
@@ -1,7 +1,7 @@
 // To turn off console in Windows build:
 //#![windows_subsystem = "windows"]
 
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, sync::Arc, time::Instant};
 
 use android_activity::WindowManagerFlags;
 use log::trace;
@@ -10,13 +10,69 @@ use wgpu::TextureFormat;
 use wgpu::{Adapter, Device, Instance, PipelineLayout, Queue, RenderPipeline, ShaderModule};
 
 use winit::{
-    event::{Event, StartCause::WaitCancelled, WindowEvent},
-    event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopWindowTarget},
+    application::ApplicationHandler,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopBuilder},
+    keyboard::Key,
+    window::WindowId,
 };
 
 #[cfg(target_os = "android")]
 use winit::platform::android::{activity::AndroidApp, EventLoopBuilderExtAndroid};
 
+#[cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, rc::Rc};
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::EventLoopExtWebSys;
+
+mod multi_touch;
+use multi_touch::{Affine, MultiTouch};
+
+mod stats;
+use stats::Stats;
+
+mod gpu_profiler;
+use gpu_profiler::GpuProfiler;
+
+#[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+mod hot_reload;
+
+/// How to pick the swapchain's surface format in `ensure_render_state_for_surface`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FormatPreference {
+    /// The original heuristic: the first sRGB format the surface supports,
+    /// falling back to whatever format comes first.
+    PreferSrgb,
+    /// Prefer a wide-gamut float/10-bit format (`Rgba16Float`, `Rgb10a2Unorm`)
+    /// for HDR output, falling back to `PreferSrgb` if the surface doesn't
+    /// support one.
+    PreferHdr,
+    /// Use exactly this format, with no fallback.
+    Explicit(TextureFormat),
+}
+
+fn format_is_hdr(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba16Float | TextureFormat::Rgb10a2Unorm
+    )
+}
+
+// Parses the handful of swapchain-suitable formats a `WGPU_FORMAT` env var
+// might reasonably name (a single name, not a list), read at build time the
+// same way as `WGPU_BACKEND`.
+fn parse_explicit_format(name: &str) -> Option<TextureFormat> {
+    Some(match name {
+        "Rgba8Unorm" => TextureFormat::Rgba8Unorm,
+        "Rgba8UnormSrgb" => TextureFormat::Rgba8UnormSrgb,
+        "Bgra8Unorm" => TextureFormat::Bgra8Unorm,
+        "Bgra8UnormSrgb" => TextureFormat::Bgra8UnormSrgb,
+        "Rgba16Float" => TextureFormat::Rgba16Float,
+        "Rgb10a2Unorm" => TextureFormat::Rgb10a2Unorm,
+        _ => return None,
+    })
+}
+
 struct RenderState {
     device: Device,
     queue: Queue,
@@ -24,6 +80,78 @@ struct RenderState {
     target_format: TextureFormat,
     _pipeline_layout: PipelineLayout,
     render_pipeline: RenderPipeline,
+    transform_buffer: wgpu::Buffer,
+    transform_bind_group: wgpu::BindGroup,
+    _stats_shader: ShaderModule,
+    stats_pipeline: RenderPipeline,
+    stats_vertex_buffer: wgpu::Buffer,
+    // `None` unless profiling was requested via `WGPU_GPU_PROFILE` and the
+    // adapter supports `Features::TIMESTAMP_QUERY`; rendering then falls back to the previous `timestamp_writes: None` path.
+    gpu_profiler: Option<GpuProfiler>,
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    hot_reloader: Option<hot_reload::HotReloader>,
+}
+
+// Embeds a 2D [`Affine`] as a column-major 4x4 matrix so it can be
+// uploaded as-is into a `mat4x4<f32>` uniform and multiplied against the
+// vertex position in `shader.wgsl`.
+fn affine_to_mat4_bytes(t: Affine) -> [u8; 64] {
+    let [a, b, c, d, tx, ty] = t;
+    #[rustfmt::skip]
+    let mat4: [f32; 16] = [
+        a,  b,  0.0, 0.0,
+        c,  d,  0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        tx, ty, 0.0, 1.0,
+    ];
+    let mut bytes = [0u8; 64];
+    for (i, v) in mat4.iter().enumerate() {
+        bytes[i * 4..i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+    }
+    bytes
+}
+
+// Two triangles (a quad) per histogram bar.
+const VERTICES_PER_BAR: usize = 6;
+
+// Builds the vertex data for the frame-time histogram: one quad per sample,
+// packed left-to-right across the bottom strip of the frame, colored from
+// green (comfortably under budget) to red (at or past it).
+fn build_stats_vertices(stats: &Stats) -> Vec<u8> {
+    const PANEL_LEFT: f32 = -1.0;
+    const PANEL_RIGHT: f32 = 1.0;
+    const PANEL_BOTTOM: f32 = -1.0;
+    const PANEL_TOP: f32 = -0.7;
+    // A 30fps frame budget; samples at or beyond this fill the panel.
+    const BUDGET_SECS: f32 = 1.0 / 30.0;
+
+    let bar_width = (PANEL_RIGHT - PANEL_LEFT) / stats::CAPACITY as f32;
+    let mut bytes = Vec::with_capacity(stats::CAPACITY * VERTICES_PER_BAR * 20);
+
+    for (i, sample) in stats.samples().enumerate() {
+        let t = (sample.as_secs_f32() / BUDGET_SECS).min(1.0);
+        let x0 = PANEL_LEFT + bar_width * i as f32;
+        let x1 = x0 + bar_width * 0.8;
+        let y1 = PANEL_BOTTOM + (PANEL_TOP - PANEL_BOTTOM) * t;
+        let color = [t, 1.0 - t, 0.1];
+
+        let quad = [
+            [x0, PANEL_BOTTOM],
+            [x1, PANEL_BOTTOM],
+            [x1, y1],
+            [x0, PANEL_BOTTOM],
+            [x1, y1],
+            [x0, y1],
+        ];
+        for pos in quad {
+            bytes.extend_from_slice(&pos[0].to_le_bytes());
+            bytes.extend_from_slice(&pos[1].to_le_bytes());
+            bytes.extend_from_slice(&color[0].to_le_bytes());
+            bytes.extend_from_slice(&color[1].to_le_bytes());
+            bytes.extend_from_slice(&color[2].to_le_bytes());
+        }
+    }
+    bytes
 }
 
 struct SurfaceState<'a> {
@@ -36,41 +164,136 @@ struct App<'a> {
     adapter: Option<Adapter>,
     surface_state: Option<SurfaceState<'a>>,
     render_state: Option<RenderState>,
+    // Whether the swapchain should be configured with `PresentMode::Fifo`
+    // (vsync on) or the fastest mode the surface supports (vsync off).
+    // Toggled at runtime with the "V" key, which is handy for benchmarking
+    // uncapped frame rates on a device where editing source isn't an option.
+    vsync: bool,
+    // Pinch/pan/rotate gesture tracking and the transform it accumulates,
+    // applied to the triangle in the vertex shader.
+    multi_touch: MultiTouch,
+    transform: Affine,
+    // Frame-time ring buffer and whether its histogram overlay is drawn,
+    // toggled at runtime with the "F" key so jank is visible without a
+    // console, which matters on Android.
+    stats: Stats,
+    show_stats: bool,
+    format_preference: FormatPreference,
+    // Whether GPU timestamp profiling was requested via `WGPU_GPU_PROFILE`;
+    // still subject to adapter support, see `init_render_state`.
+    gpu_profile_requested: bool,
     #[cfg(target_os = "android")]
     android_app: Option<AndroidApp>,
 }
 
 impl App<'_> {
-    fn new(instance: Instance) -> Self {
+    fn new(
+        instance: Instance,
+        vsync: bool,
+        format_preference: FormatPreference,
+        gpu_profile_requested: bool,
+    ) -> Self {
         Self {
             instance,
             adapter: None,
             surface_state: None,
             render_state: None,
+            vsync,
+            multi_touch: MultiTouch::new(),
+            transform: multi_touch::IDENTITY,
+            stats: Stats::new(),
+            show_stats: true,
+            format_preference,
+            gpu_profile_requested,
             #[cfg(target_os = "android")]
             android_app: None,
         }
     }
 
-    fn create_surface<T>(&mut self, elwt: &EventLoopWindowTarget<T>) {
+    // Picks the swapchain format per `self.format_preference`, logging which
+    // format (and whether it's HDR) was actually chosen.
+    fn choose_surface_format(&self, surface_caps: &wgpu::SurfaceCapabilities) -> TextureFormat {
+        Self::choose_surface_format_for(self.format_preference, surface_caps)
+    }
+
+    // Associated-function version of `choose_surface_format` for callers
+    // (namely `build_wasm_render_state`, used by `WasmAppHandler::resumed`)
+    // that only have a `FormatPreference`
+    // on hand, not a full `&App`.
+    fn choose_surface_format_for(
+        format_preference: FormatPreference,
+        surface_caps: &wgpu::SurfaceCapabilities,
+    ) -> TextureFormat {
+        let prefer_srgb = || {
+            surface_caps
+                .formats
+                .iter()
+                .copied()
+                .find(|f| f.is_srgb())
+                .unwrap_or(surface_caps.formats[0])
+        };
+
+        let format = match format_preference {
+            FormatPreference::Explicit(format) => format,
+            FormatPreference::PreferSrgb => prefer_srgb(),
+            FormatPreference::PreferHdr => surface_caps
+                .formats
+                .iter()
+                .copied()
+                .find(|f| format_is_hdr(*f))
+                .unwrap_or_else(|| {
+                    log::warn!(
+                        "WGPU: HDR format requested but not supported by this surface, falling back to sRGB"
+                    );
+                    prefer_srgb()
+                }),
+        };
+
+        log::info!(
+            "WGPU: chosen swapchain format = {format:?} (hdr = {})",
+            format_is_hdr(format)
+        );
+        format
+    }
+
+    // Picks the present mode to configure the swapchain with based on the
+    // current vsync setting. When vsync is off we prefer `Mailbox` (lets us
+    // render as fast as possible while still avoiding tearing) and otherwise
+    // fall back to `Immediate` (tearing, but uncapped) if that's all the
+    // surface supports.
+    fn present_mode(&self, surface_caps: &wgpu::SurfaceCapabilities) -> wgpu::PresentMode {
+        if self.vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+                .iter()
+                .copied()
+                .find(|mode| surface_caps.present_modes.contains(mode))
+                .unwrap_or(wgpu::PresentMode::Fifo)
+        }
+    }
+
+    fn create_surface(&mut self, event_loop: &ActiveEventLoop) {
         #[cfg(target_arch = "wasm32")]
-        let window = {
-            use winit::{dpi::PhysicalSize, platform::web::WindowBuilderExtWebSys};
-            Arc::new(
-                winit::window::WindowBuilder::new()
-                    // Automatically creates the canvas with [data-raw-handle] suitable for wgpu
-                    .with_canvas(None)
-                    // Winit prevents sizing with CSS, so we have to set
-                    // the size manually when on web.
-                    .with_inner_size(PhysicalSize::new(450, 400))
-                    .with_append(true)
-                    .build(elwt)
-                    .unwrap(),
-            )
+        let window_attributes = {
+            use winit::{dpi::PhysicalSize, platform::web::WindowAttributesExtWebSys};
+            winit::window::Window::default_attributes()
+                // Automatically creates the canvas with [data-raw-handle] suitable for wgpu
+                .with_canvas(None)
+                // Winit prevents sizing with CSS, so we have to set
+                // the size manually when on web.
+                .with_inner_size(PhysicalSize::new(450, 400))
+                .with_append(true)
         };
-        // For other platforms you could also use the WindowBuilder to set the title etc.
+        // For other platforms you could also set the window title etc. here.
         #[cfg(not(target_arch = "wasm32"))]
-        let window = Arc::new(winit::window::Window::new(elwt).unwrap());
+        let window_attributes = winit::window::Window::default_attributes();
+
+        let window = Arc::new(
+            event_loop
+                .create_window(window_attributes)
+                .expect("Failed to create window"),
+        );
 
         log::info!("WGPU: creating surface for native window");
         let surface = self
@@ -80,7 +303,41 @@ impl App<'_> {
         self.surface_state = Some(SurfaceState { window, surface });
     }
 
-    async fn init_render_state(adapter: &Adapter, target_format: TextureFormat) -> RenderState {
+    // Builds the pipeline for drawing the main triangle against `shader`.
+    // Factored out so the hot-reload path can rebuild it from new shader
+    // source without duplicating the descriptor.
+    fn build_triangle_pipeline(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        shader: &ShaderModule,
+        target_format: TextureFormat,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    async fn init_render_state(
+        adapter: &Adapter,
+        target_format: TextureFormat,
+        is_hdr: bool,
+        gpu_profile_requested: bool,
+    ) -> RenderState {
         log::info!("Initializing render state");
 
         log::info!(
@@ -89,13 +346,27 @@ impl App<'_> {
         );
         log::info!("Supports: {:?}", adapter.features());
 
+        // GPU timestamp profiling only works with adapters that advertise
+        // this feature, and is otherwise opt-in via `WGPU_GPU_PROFILE` since
+        // it's supported by nearly every desktop/mobile GPU: enabling it
+        // unconditionally would mean every user pays for the periodic
+        // `Maintain::Poll` bookkeeping in `GpuProfiler::poll_and_log` with
+        // no way to turn it off.
+        let gpu_profiling_supported =
+            gpu_profile_requested && adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        let required_features = if gpu_profiling_supported {
+            wgpu::Features::TIMESTAMP_QUERY
+        } else {
+            wgpu::Features::empty()
+        };
+
         log::info!("WGPU: requesting device");
         // Create the logical device and command queue
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: wgpu::Features::empty(),
+                    required_features,
                     // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
                     required_limits: wgpu::Limits::downlevel_webgl2_defaults()
                         .using_resolution(adapter.limits()),
@@ -105,31 +376,111 @@ impl App<'_> {
             .await
             .expect("Failed to create device");
 
+        let gpu_profiler = if gpu_profiling_supported {
+            log::info!("WGPU: GPU profiling requested and supported by adapter, enabling it");
+            Some(GpuProfiler::new(&device, &queue))
+        } else if gpu_profile_requested {
+            log::info!("WGPU: GPU profiling requested but adapter lacks TIMESTAMP_QUERY, disabling it");
+            None
+        } else {
+            None
+        };
+
         log::info!("WGPU: loading shader");
-        // Load the shaders from disk
+        // Load the shaders from disk. The HDR variant adds a tonemapping
+        // step, since it renders to a linear float or 10-bit target rather
+        // than an sRGB one.
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(if is_hdr {
+                include_str!("shader_hdr.wgsl")
+            } else {
+                include_str!("shader.wgsl")
+            })),
+        });
+
+        log::info!("WGPU: creating transform uniform buffer and bind group");
+        let transform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("transform uniform buffer"),
+            size: std::mem::size_of::<[f32; 16]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&transform_buffer, 0, &affine_to_mat4_bytes(multi_touch::IDENTITY));
+
+        let transform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("transform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let transform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("transform bind group"),
+            layout: &transform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: transform_buffer.as_entire_binding(),
+            }],
         });
 
         log::info!("WGPU: creating pipeline layout");
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&transform_bind_group_layout],
             push_constant_ranges: &[],
         });
 
         log::info!("WGPU: creating render pipeline");
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
+        let render_pipeline =
+            Self::build_triangle_pipeline(&device, &pipeline_layout, &shader, target_format);
+
+        log::info!("WGPU: loading stats overlay shader");
+        let stats_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("stats overlay shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("stats.wgsl"))),
+        });
+
+        let stats_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("stats overlay pipeline layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+
+        let stats_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("stats overlay pipeline"),
+            layout: Some(&stats_pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: &stats_shader,
                 entry_point: "vs_main",
-                buffers: &[],
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 5]>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x2,
+                            offset: 0,
+                            shader_location: 0,
+                        },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: std::mem::size_of::<[f32; 2]>() as u64,
+                            shader_location: 1,
+                        },
+                    ],
+                }],
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: &stats_shader,
                 entry_point: "fs_main",
                 targets: &[Some(target_format.into())],
             }),
@@ -139,6 +490,13 @@ impl App<'_> {
             multiview: None,
         });
 
+        let stats_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("stats overlay vertex buffer"),
+            size: (stats::CAPACITY * VERTICES_PER_BAR * std::mem::size_of::<[f32; 5]>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         RenderState {
             device,
             queue,
@@ -146,6 +504,14 @@ impl App<'_> {
             target_format,
             _pipeline_layout: pipeline_layout,
             render_pipeline,
+            transform_buffer,
+            transform_bind_group,
+            _stats_shader: stats_shader,
+            stats_pipeline,
+            stats_vertex_buffer,
+            gpu_profiler,
+            #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+            hot_reloader: None,
         }
     }
 
@@ -178,24 +544,150 @@ impl App<'_> {
                 log::info!("WGPU: finding supported swapchain format");
                 let surface_caps = surface_state.surface.get_capabilities(adapter);
 
-                let swapchain_format = surface_caps
-                    .formats
-                    .iter()
-                    .copied()
-                    .find(|f| f.is_srgb())
-                    .unwrap_or(surface_caps.formats[0]);
+                let swapchain_format = self.choose_surface_format(&surface_caps);
+                let is_hdr = format_is_hdr(swapchain_format);
+
+                let mut rs = Self::init_render_state(
+                    adapter,
+                    swapchain_format,
+                    is_hdr,
+                    self.gpu_profile_requested,
+                )
+                .await;
+
+                #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+                {
+                    let shader_file = if is_hdr { "shader_hdr.wgsl" } else { "shader.wgsl" };
+                    let shader_path = std::path::PathBuf::from(concat!(
+                        env!("CARGO_MANIFEST_DIR"),
+                        "/src/"
+                    ))
+                    .join(shader_file);
+                    rs.hot_reloader = Some(hot_reload::HotReloader::spawn(
+                        shader_path,
+                        surface_state.window.clone(),
+                    ));
+                }
 
-                let rs = Self::init_render_state(adapter, swapchain_format).await;
                 self.render_state = Some(rs);
             }
         }
     }
 
+    // Associated-function counterpart to `ensure_render_state_for_surface`
+    // for `WasmAppHandler::resumed`: takes owned/borrowed pieces instead of
+    // `&mut self` so the caller isn't forced to hold a `RefCell` borrow of
+    // the whole `App` across the adapter/device awaits below.
+    #[cfg(target_arch = "wasm32")]
+    async fn build_wasm_render_state(
+        instance: &Instance,
+        surface: &wgpu::Surface<'_>,
+        format_preference: FormatPreference,
+        gpu_profile_requested: bool,
+    ) -> (Adapter, RenderState) {
+        log::info!("WGPU: requesting a suitable adapter (compatible with our surface)");
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                force_fallback_adapter: false,
+                compatible_surface: Some(surface),
+            })
+            .await
+            .expect("Failed to find an appropriate adapter");
+
+        log::info!("WGPU: finding supported swapchain format");
+        let surface_caps = surface.get_capabilities(&adapter);
+        let swapchain_format = Self::choose_surface_format_for(format_preference, &surface_caps);
+        let is_hdr = format_is_hdr(swapchain_format);
+
+        let render_state =
+            Self::init_render_state(&adapter, swapchain_format, is_hdr, gpu_profile_requested)
+                .await;
+        (adapter, render_state)
+    }
+
+    // Rebuilds the render pipeline from newly hot-reloaded shader source, if
+    // any arrived since the last check. A shader with a compile error logs
+    // the validation diagnostic and leaves the previous working pipeline in
+    // place rather than panicking.
+    #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+    fn poll_hot_reload(&mut self) {
+        let Some(rs) = self.render_state.as_mut() else {
+            return;
+        };
+        let Some(source) = rs
+            .hot_reloader
+            .as_ref()
+            .and_then(|reloader| reloader.try_recv_latest())
+        else {
+            return;
+        };
+
+        log::info!("Hot reload: shader source changed, rebuilding pipeline");
+        rs.device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = rs.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("hot reloaded shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let pipeline = Self::build_triangle_pipeline(
+            &rs.device,
+            &rs._pipeline_layout,
+            &shader,
+            rs.target_format,
+        );
+
+        if let Some(error) = pollster::block_on(rs.device.pop_error_scope()) {
+            log::error!(
+                "Hot reload: shader failed to compile, keeping previous pipeline: {error}"
+            );
+            return;
+        }
+
+        rs.render_pipeline = pipeline;
+        rs._shader = shader;
+        self.queue_redraw();
+    }
+
     fn configure_surface_swapchain(&mut self) {
-        if let (Some(render_state), Some(surface_state)) = (&self.render_state, &self.surface_state)
+        if let (Some(render_state), Some(surface_state), Some(adapter)) =
+            (&self.render_state, &self.surface_state, &self.adapter)
         {
             let swapchain_format = render_state.target_format;
             let size = surface_state.window.inner_size();
+            let surface_caps = surface_state.surface.get_capabilities(adapter);
+            let present_mode = self.present_mode(&surface_caps);
+            let is_hdr = format_is_hdr(swapchain_format);
+
+            // Let the pipeline view the swapchain texture through its sRGB
+            // counterpart too, when the chosen format has one (e.g. a plain
+            // `Rgba8Unorm` swapchain with an `Rgba8UnormSrgb` view).
+            let srgb_format = swapchain_format.add_srgb_suffix();
+            let view_formats = if srgb_format != swapchain_format {
+                vec![swapchain_format, srgb_format]
+            } else {
+                vec![swapchain_format]
+            };
+
+            // HDR float/10-bit formats need the surface's HDR-capable alpha
+            // composition mode; anything else keeps deferring to the
+            // compositor as before.
+            let alpha_mode = if is_hdr {
+                surface_caps
+                    .alpha_modes
+                    .iter()
+                    .copied()
+                    .find(|mode| {
+                        matches!(
+                            mode,
+                            wgpu::CompositeAlphaMode::PreMultiplied
+                                | wgpu::CompositeAlphaMode::PostMultiplied
+                        )
+                    })
+                    .unwrap_or(wgpu::CompositeAlphaMode::Inherit)
+            } else {
+                wgpu::CompositeAlphaMode::Inherit
+            };
 
             let config = wgpu::SurfaceConfiguration {
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -203,14 +695,14 @@ impl App<'_> {
                 width: size.width,
                 height: size.height,
                 desired_maximum_frame_latency: 2,
-                //present_mode: wgpu::PresentMode::Mailbox,
-                present_mode: wgpu::PresentMode::Fifo,
-                view_formats: vec![swapchain_format],
-                alpha_mode: wgpu::CompositeAlphaMode::Inherit,
-                //alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+                present_mode,
+                view_formats,
+                alpha_mode,
             };
 
-            log::info!("WGPU: Configuring surface swapchain: format = {swapchain_format:?}, size = {size:?}");
+            log::info!(
+                "WGPU: Configuring surface swapchain: format = {swapchain_format:?}, hdr = {is_hdr}, size = {size:?}, present_mode = {present_mode:?}, alpha_mode = {alpha_mode:?}"
+            );
             surface_state
                 .surface
                 .configure(&render_state.device, &config);
@@ -224,7 +716,7 @@ impl App<'_> {
         }
     }
 
-    async fn resume<T>(&mut self, event_loop: &EventLoopWindowTarget<T>) {
+    async fn resume(&mut self, event_loop: &ActiveEventLoop) {
         self.create_surface(event_loop);
         self.ensure_render_state_for_surface().await;
         self.configure_surface_swapchain();
@@ -232,8 +724,25 @@ impl App<'_> {
     }
 
     fn render(&mut self) {
+        #[cfg(not(any(target_os = "android", target_arch = "wasm32")))]
+        self.poll_hot_reload();
+
         if let Some(ref surface_state) = self.surface_state {
-            if let Some(ref rs) = self.render_state {
+            if let Some(ref mut rs) = self.render_state {
+                rs.queue.write_buffer(
+                    &rs.transform_buffer,
+                    0,
+                    &affine_to_mat4_bytes(self.transform),
+                );
+
+                let stats_vertex_count = if self.show_stats {
+                    let vertices = build_stats_vertices(&self.stats);
+                    rs.queue.write_buffer(&rs.stats_vertex_buffer, 0, &vertices);
+                    (vertices.len() / std::mem::size_of::<[f32; 5]>()) as u32
+                } else {
+                    0
+                };
+
                 let frame = surface_state
                     .surface
                     .get_current_texture()
@@ -257,15 +766,37 @@ impl App<'_> {
                         })],
                         depth_stencil_attachment: None,
                         occlusion_query_set: None,
-                        timestamp_writes: None,
+                        timestamp_writes: rs
+                            .gpu_profiler
+                            .as_ref()
+                            .and_then(|p| p.timestamp_writes()),
                     });
                     rpass.set_pipeline(&rs.render_pipeline);
+                    rpass.set_bind_group(0, &rs.transform_bind_group, &[]);
                     rpass.draw(0..3, 0..1);
+
+                    if stats_vertex_count > 0 {
+                        rpass.set_pipeline(&rs.stats_pipeline);
+                        rpass.set_vertex_buffer(0, rs.stats_vertex_buffer.slice(..));
+                        rpass.draw(0..stats_vertex_count, 0..1);
+                    }
+                }
+
+                if let Some(ref mut profiler) = rs.gpu_profiler {
+                    profiler.resolve(&mut encoder);
                 }
 
                 rs.queue.submit(Some(encoder.finish()));
                 frame.present();
 
+                if let Some(ref mut profiler) = rs.gpu_profiler {
+                    // Non-blocking: kicks off this frame's mapping and logs
+                    // whichever earlier frame's mapping has since completed,
+                    // rather than stalling on `Maintain::Wait` every frame.
+                    profiler.begin_readback();
+                    profiler.poll_and_log(&rs.device);
+                }
+
                 // To animate, uncomment this to request the next frame:
                 //surface_state.window.request_redraw();
             }
@@ -273,79 +804,192 @@ impl App<'_> {
     }
 }
 
+impl ApplicationHandler for App<'_> {
+    // On Android the native window (and EGL/Vulkan surface bound to it) is
+    // destroyed by the OS whenever the app backgrounds, so `suspended`
+    // drops both `render_state` and `surface_state` rather than just the
+    // former: `SurfaceState` must be fully reconstructable from scratch
+    // here, since its `window` may no longer be valid by the time we're
+    // resumed again.
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        log::info!("Resumed, (re)creating surface and render state...");
+        #[cfg(not(target_arch = "wasm32"))]
+        pollster::block_on(self.resume(event_loop));
+        // wasm32's device/adapter setup is async, and `resumed` isn't, so
+        // on the web target this method is never actually reached:
+        // `WasmAppHandler` (see `run`) intercepts `resumed` itself and
+        // bridges the async setup through `wasm_bindgen_futures::spawn_local`
+        // instead. Kept here only so `App` still implements the full trait.
+        #[cfg(target_arch = "wasm32")]
+        let _ = event_loop;
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        log::info!("Suspended, dropping render and surface state...");
+        self.render_state = None;
+        self.surface_state = None;
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::Resized(_size) => {
+                self.configure_surface_swapchain();
+                // Winit: doesn't currently implicitly request a redraw
+                // for a resize which may be required on some platforms...
+                self.queue_redraw();
+            }
+            WindowEvent::RedrawRequested => {
+                log::info!("Handling Redraw Request");
+                let frame_start = Instant::now();
+                self.render();
+                self.stats.add_sample(frame_start.elapsed());
+            }
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CursorMoved { .. } => {
+                // not logged, contains mouse motion
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(ref key),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if key.eq_ignore_ascii_case("v") => {
+                self.vsync = !self.vsync;
+                log::info!("Vsync toggled: {}", self.vsync);
+                self.configure_surface_swapchain();
+                self.queue_redraw();
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Character(ref key),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } if key.eq_ignore_ascii_case("f") => {
+                self.show_stats = !self.show_stats;
+                log::info!(
+                    "Stats overlay toggled: {} (fps = {:.1}, min = {:.2}ms, max = {:.2}ms)",
+                    self.show_stats,
+                    self.stats.fps(),
+                    self.stats.min().as_secs_f64() * 1000.0,
+                    self.stats.max().as_secs_f64() * 1000.0,
+                );
+                self.queue_redraw();
+            }
+            WindowEvent::Touch(touch) => {
+                // Demonstration of showing onscreen keyboard.
+                // show_implicit argument means something other than
+                // a literal "open keyboard" button was pressed
+                #[cfg(target_os = "android")]
+                {
+                    log::info!("check");
+                    self.android_app.as_ref().unwrap().show_soft_input(false);
+                }
+                self.multi_touch
+                    .on_touch(touch.id, touch.phase, touch.location, &mut self.transform);
+                self.queue_redraw();
+            }
+            _ => {
+                log::info!("Window event {:#?}", event);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn run(event_loop: EventLoop<()>, mut app: App) {
     log::info!("Running mainloop...");
     event_loop.set_control_flow(ControlFlow::Wait);
+    event_loop.run_app(&mut app).ok();
+}
 
-    event_loop
-        .run(move |event, elwt| {
-            match event {
-                Event::Resumed => {
-                    log::info!("Resumed, creating render state...");
-                    #[cfg(not(target_arch = "wasm32"))]
-                    pollster::block_on(app.resume(&elwt));
-                }
-                Event::Suspended => {
-                    log::info!("Suspended, dropping render state...");
-                    app.render_state = None;
-                }
-                Event::WindowEvent {
-                    event: WindowEvent::Resized(_size),
-                    ..
-                } => {
-                    app.configure_surface_swapchain();
-                    // Winit: doesn't currently implicitly request a redraw
-                    // for a resize which may be required on some platforms...
-                    app.queue_redraw();
-                }
-                Event::WindowEvent {
-                    event: WindowEvent::RedrawRequested,
-                    ..
-                } => {
-                    log::info!("Handling Redraw Request");
-                    app.render();
-                }
-                Event::WindowEvent {
-                    event: WindowEvent::CloseRequested,
-                    ..
-                } => elwt.exit(),
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::CursorMoved { .. } => {
-                        // not logged, contains mouse motion
-                    }
-                    #[cfg(target_os = "android")]
-                    WindowEvent::Touch { .. } => {
-                        // Demonstration of showing onscreen keyboard.
-                        // show_implicit argument means something other than
-                        // a literal "open keyboard" button was pressed
-                        log::info!("check");
-                        app.android_app.as_ref().unwrap().show_soft_input(false);
-                    }
-                    _ => {
-                        log::info!("Window event {:#?}", event);
-                    }
-                },
-                Event::AboutToWait => {
-                    // not logged
-                }
-                Event::NewEvents(WaitCancelled {
-                    start: _,
-                    requested_resume: _,
-                }) => {
-                    // not logged
-                }
-                Event::DeviceEvent {
-                    device_id: _,
-                    event: _,
-                } => {
-                    // not logged, contains mouse motion
-                }
-                _ => {
-                    log::info!("Unhandled event: {event:?}");
-                }
-            }
-        })
-        .ok();
+// `ActiveEventLoop::create_window` (used by `App::create_surface`) is only
+// callable from inside an `ApplicationHandler` callback, so unlike the old
+// winit 0.29 closure-based loop we can no longer create the surface and
+// `await` its render state up front in `_main` before the loop starts. On
+// native that's fine: `resumed` just blocks on the async setup with
+// `pollster::block_on`. On the web, blocking the only thread would freeze
+// the page, so `WasmAppHandler` wraps `App` in `Rc<RefCell<_>>` and bridges
+// `resumed` through `wasm_bindgen_futures::spawn_local` instead, handing
+// `window_event`/`suspended` straight through to `App`'s own impl.
+#[cfg(target_arch = "wasm32")]
+struct WasmAppHandler<'a>(Rc<RefCell<App<'a>>>);
+
+#[cfg(target_arch = "wasm32")]
+impl ApplicationHandler for WasmAppHandler<'_> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        log::info!("Resumed, (re)creating surface and render state...");
+        self.0.borrow_mut().create_surface(event_loop);
+
+        let handle = self.0.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            // Take ownership of just what the adapter/device setup needs,
+            // rather than holding a `RefCell` borrow across the awaits
+            // below -- so a window event that reaches `WasmAppHandler`
+            // while this is in flight (a resize during initial page
+            // layout, say) can still `borrow_mut()` the app normally
+            // instead of hitting an already-borrowed panic.
+            let (instance, surface_state, format_preference, gpu_profile_requested) = {
+                let mut app = handle.borrow_mut();
+                let Some(surface_state) = app.surface_state.take() else {
+                    return;
+                };
+                (
+                    app.instance.clone(),
+                    surface_state,
+                    app.format_preference,
+                    app.gpu_profile_requested,
+                )
+            };
+
+            let (adapter, render_state) = App::build_wasm_render_state(
+                &instance,
+                &surface_state.surface,
+                format_preference,
+                gpu_profile_requested,
+            )
+            .await;
+
+            let mut app = handle.borrow_mut();
+            app.adapter = Some(adapter);
+            app.surface_state = Some(surface_state);
+            app.render_state = Some(render_state);
+            app.configure_surface_swapchain();
+            app.queue_redraw();
+        });
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.0.borrow_mut().suspended(event_loop);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        self.0.borrow_mut().window_event(event_loop, window_id, event);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn run(event_loop: EventLoop<()>, app: App<'static>) {
+    log::info!("Running mainloop...");
+    event_loop.set_control_flow(ControlFlow::Wait);
+    event_loop.spawn_app(WasmAppHandler(Rc::new(RefCell::new(app))));
 }
 
 async fn _main(#[cfg(target_os = "android")] android_app: AndroidApp) {
@@ -363,13 +1007,43 @@ async fn _main(#[cfg(target_os = "android")] android_app: AndroidApp) {
         ..Default::default()
     });
 
-    #[allow(unused_mut)]
-    let mut app = App::new(instance);
+    // On Android, rebinding keys at runtime isn't practical, so starting
+    // vsync-off state is controlled via an env var baked in at build time,
+    // the same way `WGPU_BACKEND` is above.
+    let vsync = option_env!("WGPU_VSYNC_OFF").is_none();
+    if !vsync {
+        log::info!("Starting with vsync off (WGPU_VSYNC_OFF set)");
+    }
 
-    // spawn_local causes ownership troubles in the event loop closure
-    // so just create the surface here
-    #[cfg(target_arch = "wasm32")]
-    app.resume(&event_loop).await;
+    let format_preference = if let Some(format_name) = option_env!("WGPU_FORMAT") {
+        match parse_explicit_format(format_name) {
+            Some(format) => {
+                log::info!("Using explicit swapchain format {format:?} (WGPU_FORMAT set)");
+                FormatPreference::Explicit(format)
+            }
+            None => {
+                log::warn!("WGPU_FORMAT={format_name:?} not recognized, ignoring");
+                FormatPreference::PreferSrgb
+            }
+        }
+    } else if option_env!("WGPU_HDR").is_some() {
+        log::info!("Requesting HDR swapchain format (WGPU_HDR set)");
+        FormatPreference::PreferHdr
+    } else {
+        FormatPreference::PreferSrgb
+    };
+
+    // GPU timestamp profiling is supported by nearly every desktop/mobile
+    // adapter, so it's gated behind an explicit opt-in rather than enabled
+    // whenever the hardware allows it -- see `WGPU_GPU_PROFILE` usage in
+    // `init_render_state`.
+    let gpu_profile_requested = option_env!("WGPU_GPU_PROFILE").is_some();
+    if gpu_profile_requested {
+        log::info!("GPU timestamp profiling requested (WGPU_GPU_PROFILE set)");
+    }
+
+    #[allow(unused_mut)]
+    let mut app = App::new(instance, vsync, format_preference, gpu_profile_requested);
 
     #[cfg(target_os = "android")]
     let event_loop = {
@@ -0,0 +1,151 @@
+// Multi-touch gesture recognition: turns raw `WindowEvent::Touch` pointer
+// updates into pan/zoom/rotate deltas applied to an accumulated 2D affine
+// transform.
+
+use std::collections::HashMap;
+
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+/// A 2D affine transform `[a, b, c, d, tx, ty]`, applied to a point as
+/// `x' = a*x + c*y + tx`, `y' = b*x + d*y + ty`.
+pub type Affine = [f32; 6];
+
+pub const IDENTITY: Affine = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Composes two affine transforms, applying `inner` first and then `outer`.
+fn compose(outer: Affine, inner: Affine) -> Affine {
+    let [a, b, c, d, tx, ty] = outer;
+    let [a2, b2, c2, d2, tx2, ty2] = inner;
+    [
+        a * a2 + c * b2,
+        b * a2 + d * b2,
+        a * c2 + c * d2,
+        b * c2 + d * d2,
+        a * tx2 + c * ty2 + tx,
+        b * tx2 + d * ty2 + ty,
+    ]
+}
+
+fn translate(dx: f32, dy: f32) -> Affine {
+    [1.0, 0.0, 0.0, 1.0, dx, dy]
+}
+
+/// Builds the incremental transform that takes points near `prev_pivot` to
+/// `cur_pivot`, scaled by `scale` and rotated by `rotation` (radians) about
+/// that pivot.
+fn pivoted(scale: f32, rotation: f32, prev_pivot: (f32, f32), cur_pivot: (f32, f32)) -> Affine {
+    let (sin, cos) = rotation.sin_cos();
+    let a = scale * cos;
+    let b = scale * sin;
+    let c = -scale * sin;
+    let d = scale * cos;
+    let tx = cur_pivot.0 - (a * prev_pivot.0 + c * prev_pivot.1);
+    let ty = cur_pivot.1 - (b * prev_pivot.0 + d * prev_pivot.1);
+    [a, b, c, d, tx, ty]
+}
+
+/// Tracks active touch pointers keyed by touch id and turns one- and
+/// two-finger gestures into incremental updates of an [`Affine`] transform.
+pub struct MultiTouch {
+    pointers: HashMap<u64, PhysicalPosition<f64>>,
+    // The two touch positions from the previous two-finger update, used to
+    // compute this update's pinch/pan/rotate delta.
+    prev_two: Option<(PhysicalPosition<f64>, PhysicalPosition<f64>)>,
+}
+
+impl MultiTouch {
+    pub fn new() -> Self {
+        Self {
+            pointers: HashMap::new(),
+            prev_two: None,
+        }
+    }
+
+    /// Feed a single touch event into the tracker, accumulating any
+    /// resulting gesture into `transform`.
+    pub fn on_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: PhysicalPosition<f64>,
+        transform: &mut Affine,
+    ) {
+        let prev_position = self.pointers.get(&id).copied();
+
+        match phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                self.pointers.insert(id, position);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.pointers.remove(&id);
+            }
+        }
+
+        if self.pointers.len() == 2 {
+            let mut touches = self.pointers.values().copied();
+            let cur = (touches.next().unwrap(), touches.next().unwrap());
+            if let Some(prev) = self.prev_two {
+                self.apply_pinch_pan_rotate(prev, cur, transform);
+            }
+            self.prev_two = Some(cur);
+            return;
+        }
+        self.prev_two = None;
+
+        // Single-finger drag: just pan.
+        if phase == TouchPhase::Moved && self.pointers.len() == 1 {
+            if let Some(prev) = prev_position {
+                let dx = (position.x - prev.x) as f32;
+                let dy = (position.y - prev.y) as f32;
+                *transform = compose(translate(dx, dy), *transform);
+            }
+        }
+    }
+
+    fn apply_pinch_pan_rotate(
+        &self,
+        prev: (PhysicalPosition<f64>, PhysicalPosition<f64>),
+        cur: (PhysicalPosition<f64>, PhysicalPosition<f64>),
+        transform: &mut Affine,
+    ) {
+        let centroid = |a: PhysicalPosition<f64>, b: PhysicalPosition<f64>| {
+            ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+        };
+        let vector = |a: PhysicalPosition<f64>, b: PhysicalPosition<f64>| (b.x - a.x, b.y - a.y);
+
+        let prev_centroid = centroid(prev.0, prev.1);
+        let cur_centroid = centroid(cur.0, cur.1);
+
+        let prev_vec = vector(prev.0, prev.1);
+        let cur_vec = vector(cur.0, cur.1);
+
+        let prev_dist = (prev_vec.0.powi(2) + prev_vec.1.powi(2)).sqrt();
+        let cur_dist = (cur_vec.0.powi(2) + cur_vec.1.powi(2)).sqrt();
+
+        // Below this separation, the two touches are close enough together
+        // that `atan2`/the distance ratio are dominated by per-sample touch
+        // jitter rather than an intentional pinch/rotate -- a noisy scale or
+        // rotation computed here gets composed permanently into `transform`
+        // with no way to undo it, so skip scale/rotation entirely and fall
+        // back to a plain pan by centroid movement.
+        const MIN_GESTURE_DISTANCE_PX: f64 = 8.0;
+        if prev_dist < MIN_GESTURE_DISTANCE_PX || cur_dist < MIN_GESTURE_DISTANCE_PX {
+            let dx = (cur_centroid.0 - prev_centroid.0) as f32;
+            let dy = (cur_centroid.1 - prev_centroid.1) as f32;
+            *transform = compose(translate(dx, dy), *transform);
+            return;
+        }
+
+        let scale = (cur_dist / prev_dist) as f32;
+        let rotation = (cur_vec.1.atan2(cur_vec.0) - prev_vec.1.atan2(prev_vec.0)) as f32;
+
+        let delta = pivoted(
+            scale,
+            rotation,
+            (prev_centroid.0 as f32, prev_centroid.1 as f32),
+            (cur_centroid.0 as f32, cur_centroid.1 as f32),
+        );
+        *transform = compose(delta, *transform);
+    }
+}
@@ -0,0 +1,60 @@
+// Frame-time statistics, collected into a fixed-capacity ring buffer and
+// summarized for the on-screen overlay. Loosely modeled on Vello's `stats`
+// module: a device screen doubles as the only console most Android builds
+// of this example ever get, so the numbers need to be visible there.
+
+use std::time::Duration;
+
+pub const CAPACITY: usize = 100;
+
+pub struct Stats {
+    samples: [Duration; CAPACITY],
+    len: usize,
+    next: usize,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            samples: [Duration::ZERO; CAPACITY],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    pub fn add_sample(&mut self, frame_time: Duration) {
+        self.samples[self.next] = frame_time;
+        self.next = (self.next + 1) % CAPACITY;
+        self.len = (self.len + 1).min(CAPACITY);
+    }
+
+    /// Samples oldest-first.
+    pub fn samples(&self) -> impl Iterator<Item = Duration> + '_ {
+        let start = if self.len < CAPACITY { 0 } else { self.next };
+        (0..self.len).map(move |i| self.samples[(start + i) % CAPACITY])
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples().min().unwrap_or(Duration::ZERO)
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples().max().unwrap_or(Duration::ZERO)
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.len == 0 {
+            return Duration::ZERO;
+        }
+        self.samples().sum::<Duration>() / self.len as u32
+    }
+
+    pub fn fps(&self) -> f64 {
+        let mean = self.mean();
+        if mean.is_zero() {
+            0.0
+        } else {
+            1.0 / mean.as_secs_f64()
+        }
+    }
+}